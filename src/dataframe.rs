@@ -0,0 +1,73 @@
+// Copyright 2018 Grove Enterprises LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::Error;
+
+use super::api::*;
+use super::exec::ExecutionError;
+
+/// Errors surfaced by the `DataFrame` builder API, as distinct from `ExecutionError`, which
+/// covers failures while a plan produced from it is actually scanning rows
+#[derive(Debug)]
+pub enum DataFrameError {
+    IoError(Error),
+    ExecutionError(ExecutionError),
+    InvalidColumn(String)
+}
+
+impl From<Error> for DataFrameError {
+    fn from(e: Error) -> Self {
+        DataFrameError::IoError(e)
+    }
+}
+
+impl From<ExecutionError> for DataFrameError {
+    fn from(e: ExecutionError) -> Self {
+        DataFrameError::ExecutionError(e)
+    }
+}
+
+/// A lazily-built query, represented as a `LogicalPlan` under the hood. The query-building
+/// methods (`select`, `filter`) return a new `DataFrame` wrapping an extended plan rather than
+/// mutating `self`, so a `DataFrame` can be freely reused as the common prefix of several
+/// downstream queries; `write`, `collect`, `count` and `take` execute the plan.
+pub trait DataFrame {
+    /// Create a projection
+    fn select(&self, expr: Vec<Expr>) -> Result<Box<DataFrame>, DataFrameError>;
+
+    /// Create a selection
+    fn filter(&self, expr: Expr) -> Result<Box<DataFrame>, DataFrameError>;
+
+    /// Write the results to a CSV file
+    fn write(&self, filename: &str) -> Result<(), DataFrameError>;
+
+    /// Execute the plan and materialize every resulting row in memory
+    fn collect(&self) -> Result<Vec<Row>, DataFrameError>;
+
+    /// Execute the plan and return the number of rows it produces
+    fn count(&self) -> Result<usize, DataFrameError>;
+
+    /// Execute the plan, limited to the first `n` rows
+    fn take(&self, n: usize) -> Result<Vec<Row>, DataFrameError>;
+
+    /// Create an expression that references a column by name, e.g. "id" or a join-qualified
+    /// "t.id"
+    fn col(&self, column_name: &str) -> Result<Expr, DataFrameError>;
+
+    /// Return the schema this `DataFrame` would produce if executed
+    fn schema(&self) -> Schema;
+
+    /// Repartition the data into `n` partitions
+    fn repartition(&self, n: u32) -> Result<Box<DataFrame>, DataFrameError>;
+}
@@ -13,6 +13,8 @@
 // limitations under the License.
 
 use std::collections::HashMap;
+use std::collections::BinaryHeap;
+use std::cmp::Ordering;
 use std::io::Error;
 use std::io::BufReader;
 use std::io::prelude::*;
@@ -20,6 +22,7 @@ use std::iter::Iterator;
 use std::fs::File;
 use std::string::String;
 use std::convert::*;
+use std::process;
 
 extern crate csv;
 
@@ -63,7 +66,10 @@ impl From<ParserError> for ExecutionError {
 #[derive(Debug)]
 pub struct CsvRelation {
     file: File,
-    schema: Schema
+    schema: Schema,
+    /// a field value equal to this sentinel (e.g. "NA", "\N") is treated as NULL for
+    /// nullable columns, in addition to an empty field
+    null_sentinel: Option<String>
 }
 
 pub struct FilterRelation {
@@ -84,23 +90,79 @@ pub struct LimitRelation {
     limit: usize,
 }
 
+/// A relation backed by rows already held in memory, rather than a file or another
+/// relation. Used to serve `LogicalPlan::NamedReference` for the working set of a recursive
+/// query, since that working set was produced by a previous iteration rather than read fresh.
+pub struct InMemoryRelation {
+    schema: Schema,
+    rows: Vec<Row>
+}
+
+impl SimpleRelation for InMemoryRelation {
+    fn scan<'a>(&'a self, _ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        Box::new(self.rows.iter().cloned().map(Ok))
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
 impl<'a> CsvRelation {
 
     pub fn open(file: File, schema: Schema) -> Result<Self,ExecutionError> {
-        Ok(CsvRelation { file, schema })
+        Ok(CsvRelation { file, schema, null_sentinel: None })
     }
 
-    /// Convert StringRecord into our internal tuple type based on the known schema
-    fn create_tuple(&self, r: &StringRecord) -> Result<Row,ExecutionError> {
+    /// Open a CSV file that also treats `sentinel` (e.g. "NA", "\N") as NULL for nullable
+    /// columns, in addition to an empty field
+    pub fn open_with_null_sentinel(file: File, schema: Schema, sentinel: &str) -> Result<Self,ExecutionError> {
+        Ok(CsvRelation { file, schema, null_sentinel: Some(sentinel.to_string()) })
+    }
+
+    /// Convert StringRecord into our internal tuple type based on the known schema.
+    /// `row_num` is the zero-based record number within this file, used only to give
+    /// parse errors some context.
+    fn create_tuple(&self, r: &StringRecord, row_num: usize) -> Result<Row,ExecutionError> {
         assert_eq!(self.schema.columns.len(), r.len());
-        let values = self.schema.columns.iter().zip(r.into_iter()).map(|(c,s)| match c.data_type {
-            //TODO: remove unwrap use here
-            DataType::UnsignedLong => Value::UnsignedLong(s.parse::<u64>().unwrap()),
-            DataType::String => Value::String(s.to_string()),
-            DataType::Double => Value::Double(s.parse::<f64>().unwrap()),
-            _ => panic!("csv unsupported type")
-        }).collect();
-        Ok(Row::new(values))
+        match self.null_sentinel {
+            Some(ref sentinel) => {
+                let normalized: Vec<&str> = r.iter().map(|s| if s == sentinel { "" } else { s }).collect();
+                parse_record(&self.schema, &StringRecord::from(normalized), row_num)
+            },
+            None => parse_record(&self.schema, r, row_num)
+        }
+    }
+}
+
+/// Parse a CSV record into a `Row` using `schema`'s column types. Shared by `CsvRelation`
+/// and `SortRelation`'s external merge sort, since a spilled run is itself a small CSV file.
+fn parse_record(schema: &Schema, r: &StringRecord, row_num: usize) -> Result<Row,ExecutionError> {
+    let values = schema.columns.iter().zip(r.into_iter())
+        .map(|(c, s)| parse_value(c, s, row_num))
+        .collect::<Result<Vec<Value>, ExecutionError>>()?;
+    Ok(Row::new(values))
+}
+
+/// Parse a single CSV field according to its column's declared type. An empty field on a
+/// nullable column becomes `Value::Null` instead of being parsed; any other genuine parse
+/// failure is reported with row/column context rather than panicking via `unwrap`.
+//TODO: `rel.rs` is not part of this checkout, so the `Null` variant this function returns
+// below still needs adding to the `Value` enum there — every `Value::Null` in this file
+// assumes it already exists
+fn parse_value(column: &Field, s: &str, row_num: usize) -> Result<Value, ExecutionError> {
+    if s.is_empty() && column.nullable {
+        return Ok(Value::Null);
+    }
+    match column.data_type {
+        DataType::UnsignedLong => s.parse::<u64>()
+            .map(Value::UnsignedLong)
+            .map_err(|e| ExecutionError::Custom(format!("row {}, column \"{}\": cannot parse \"{}\" as an unsigned long: {}", row_num, column.name, s, e))),
+        DataType::String => Ok(Value::String(s.to_string())),
+        DataType::Double => s.parse::<f64>()
+            .map(Value::Double)
+            .map_err(|e| ExecutionError::Custom(format!("row {}, column \"{}\": cannot parse \"{}\" as a double: {}", row_num, column.name, s, e))),
+        _ => Err(ExecutionError::Custom(format!("row {}, column \"{}\": unsupported CSV data type", row_num, column.name)))
     }
 }
 
@@ -121,8 +183,8 @@ impl SimpleRelation for CsvRelation {
         let csv_reader = csv::Reader::from_reader(buf_reader);
         let record_iter = csv_reader.into_records();
 
-        let tuple_iter = record_iter.map(move|r| match r {
-            Ok(record) => self.create_tuple(&record),
+        let tuple_iter = record_iter.enumerate().map(move|(row_num, r)| match r {
+            Ok(record) => self.create_tuple(&record, row_num),
             Err(e) => Err(ExecutionError::CsvError(e))
         });
 
@@ -133,60 +195,1066 @@ impl SimpleRelation for CsvRelation {
         &self.schema
     }
 
-}
-
-impl SimpleRelation for FilterRelation {
+}
+
+impl SimpleRelation for FilterRelation {
+
+    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        Box::new(self.input.scan(ctx).filter_map(move|t| match t {
+            Ok(tuple) => match ctx.evaluate(&tuple, &self.schema, &self.expr) {
+                Ok(Value::Boolean(b)) => if b { Some(Ok(tuple)) } else { None },
+                Ok(_) => Some(Err(ExecutionError::Custom("predicate expression evaluated to a non-boolean value".to_string()))),
+                Err(e) => Some(Err(*e))
+            },
+            Err(e) => Some(Err(e)) // let errors through the filter so they can be handled later
+        }))
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
+impl SimpleRelation for ProjectRelation {
+
+    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        let foo = self.input.scan(ctx).map(move|r| match r {
+            Ok(tuple) => {
+                let values = self.expr.iter()
+                    .map(|e| match e {
+                        &Expr::TupleValue(i) => Ok(tuple.values[i].clone()),
+                        //TODO: relation delegating back to execution context seems wrong way around
+                        _ => ctx.evaluate(&tuple, &self.schema, e).map_err(|e| *e)
+                        //unimplemented!("Unsupported expression for projection")
+                    })
+                    .collect::<Result<Vec<Value>, ExecutionError>>()?;
+                Ok(Row::new(values))
+            },
+            Err(e) => Err(e)
+        });
+
+        Box::new(foo)
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
+impl SimpleRelation for LimitRelation {
+    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        Box::new(self.input.scan(ctx).take(self.limit))
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
+/// Running state for a single aggregate expression within a group
+#[derive(Clone, Debug)]
+pub enum Accumulator {
+    Count(u64),
+    Sum(f64, bool),
+    Min(Option<Value>),
+    Max(Option<Value>),
+    Avg(f64, u64)
+}
+
+impl Accumulator {
+    fn accumulate(&mut self, value: &Value) -> Result<(), ExecutionError> {
+        // SQL aggregate functions ignore a null in their argument rather than letting it
+        // poison the running total (or panic, since a nullable column can now yield one)
+        if *value == Value::Null {
+            return Ok(());
+        }
+        match *self {
+            Accumulator::Count(ref mut n) => *n += 1,
+            Accumulator::Sum(ref mut n, ref mut seen) => {
+                *n += Accumulator::as_f64(value)?;
+                *seen = true;
+            },
+            Accumulator::Min(ref mut acc) => {
+                let replace = match *acc {
+                    Some(ref cur) => value < cur,
+                    None => true
+                };
+                if replace {
+                    *acc = Some(value.clone());
+                }
+            },
+            Accumulator::Max(ref mut acc) => {
+                let replace = match *acc {
+                    Some(ref cur) => value > cur,
+                    None => true
+                };
+                if replace {
+                    *acc = Some(value.clone());
+                }
+            },
+            Accumulator::Avg(ref mut sum, ref mut count) => {
+                *sum += Accumulator::as_f64(value)?;
+                *count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Produce the final value for this accumulator once the input is exhausted. None of
+    /// `Sum`/`Min`/`Max`/`Avg` ever saw a non-null value when the group's column was
+    /// `Value::Null` on every row (or the group itself is empty, for the seeded
+    /// global-aggregate case), so they all report `Value::Null` rather than a poisoned or
+    /// misleadingly-zero running total.
+    fn finish(&self) -> Value {
+        match *self {
+            Accumulator::Count(n) => Value::UnsignedLong(n),
+            Accumulator::Sum(n, seen) => if seen { Value::Double(n) } else { Value::Null },
+            Accumulator::Min(ref acc) => acc.clone().unwrap_or(Value::Null),
+            Accumulator::Max(ref acc) => acc.clone().unwrap_or(Value::Null),
+            Accumulator::Avg(sum, count) => if count == 0 { Value::Null } else { Value::Double(sum / count as f64) }
+        }
+    }
+
+    //TODO: this only copes with the numeric types we currently parse out of CSV
+    fn as_f64(value: &Value) -> Result<f64, ExecutionError> {
+        match *value {
+            Value::UnsignedLong(n) => Ok(n as f64),
+            Value::Double(n) => Ok(n),
+            _ => Err(ExecutionError::Custom("aggregate function applied to a non-numeric value".to_string()))
+        }
+    }
+
+    fn for_function(name: &str) -> Result<Accumulator, ExecutionError> {
+        match name.to_lowercase().as_ref() {
+            "count" => Ok(Accumulator::Count(0)),
+            "sum" => Ok(Accumulator::Sum(0.0, false)),
+            "min" => Ok(Accumulator::Min(None)),
+            "max" => Ok(Accumulator::Max(None)),
+            "avg" => Ok(Accumulator::Avg(0.0, 0)),
+            _ => Err(ExecutionError::Custom(format!("unsupported aggregate function {}", name)))
+        }
+    }
+}
+
+/// Implements `GROUP BY` with `COUNT`/`SUM`/`MIN`/`MAX`/`AVG` as a hash aggregate.
+///
+/// `aggr_expr` reuses `Expr::ScalarFunction` (e.g. `sum(Expr::TupleValue(i))`) rather than
+/// introducing a dedicated aggregate expression node, so the group-by column values evaluate
+/// the same way a projection would.
+pub struct AggregateRelation {
+    schema: Schema,
+    input: Box<SimpleRelation>,
+    group_expr: Vec<Expr>,
+    aggr_expr: Vec<Expr>
+}
+
+impl AggregateRelation {
+
+    /// Drain the input once into a hash table keyed by the evaluated group expressions
+    fn materialize(&self, ctx: &ExecutionContext) -> Result<HashMap<Vec<Value>, Vec<Accumulator>>, ExecutionError> {
+        let mut groups: HashMap<Vec<Value>, Vec<Accumulator>> = HashMap::new();
+
+        for t in self.input.scan(ctx) {
+            let tuple = t?;
+
+            let key = self.group_expr.iter()
+                .map(|e| ctx.evaluate(&tuple, self.input.schema(), e))
+                .collect::<Result<Vec<Value>, Box<ExecutionError>>>()
+                .map_err(|e| *e)?;
+
+            if !groups.contains_key(&key) {
+                groups.insert(key.clone(), AggregateRelation::new_accumulators(&self.aggr_expr)?);
+            }
+            let accumulators = groups.get_mut(&key).unwrap();
+
+            for (acc, e) in accumulators.iter_mut().zip(self.aggr_expr.iter()) {
+                let arg = match *e {
+                    Expr::ScalarFunction { ref args, .. } => &args[0],
+                    _ => return Err(ExecutionError::Custom("unsupported aggregate expression: expected a scalar/aggregate function call".to_string()))
+                };
+                let value = ctx.evaluate(&tuple, self.input.schema(), arg).map_err(|e| *e)?;
+                acc.accumulate(&value)?;
+            }
+        }
+
+        // a global aggregate (no GROUP BY, i.e. an empty `group_expr`) must still produce its
+        // one row over empty input, e.g. `SELECT COUNT(*) FROM t WHERE false` returns a single
+        // row with COUNT=0 rather than no rows at all; a grouped aggregate has no such row to
+        // seed since every group key comes from an input row
+        if self.group_expr.is_empty() && groups.is_empty() {
+            groups.insert(Vec::new(), AggregateRelation::new_accumulators(&self.aggr_expr)?);
+        }
+
+        Ok(groups)
+    }
+
+    /// Build one fresh `Accumulator` per aggregate expression, erroring out on an unsupported
+    /// function name or a non-`ScalarFunction` aggregate expression rather than panicking, since
+    /// `aggr_expr` is only reachable by hand-building a `LogicalPlan` until `SqlToRel` wiring
+    /// for `GROUP BY` lands
+    fn new_accumulators(aggr_expr: &[Expr]) -> Result<Vec<Accumulator>, ExecutionError> {
+        aggr_expr.iter().map(|e| match *e {
+            Expr::ScalarFunction { ref name, .. } => Accumulator::for_function(name),
+            _ => Err(ExecutionError::Custom("unsupported aggregate expression: expected a scalar/aggregate function call".to_string()))
+        }).collect()
+    }
+}
+
+impl SimpleRelation for AggregateRelation {
+
+    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        // hash aggregation is not streaming: the input must be fully drained before the first
+        // output row can be produced, so we materialize eagerly and return the results as an
+        // iterator over an owned Vec
+        match self.materialize(ctx) {
+            Ok(groups) => {
+                let results: Vec<Result<Row, ExecutionError>> = groups.into_iter().map(|(key, accumulators)| {
+                    let mut values = key;
+                    values.extend(accumulators.iter().map(|a| a.finish()));
+                    Ok(Row::new(values))
+                }).collect();
+                Box::new(results.into_iter())
+            },
+            Err(e) => Box::new(vec![Err(e)].into_iter())
+        }
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
+/// Implements an inner join as a classic hash join: the right (build) side is fully
+/// materialized into a hash table keyed by its join keys, then the left (probe) side is
+/// streamed and each row is concatenated with every matching right row.
+pub struct JoinRelation {
+    schema: Schema,
+    left: Box<SimpleRelation>,
+    right: Box<SimpleRelation>,
+    left_keys: Vec<Expr>,
+    right_keys: Vec<Expr>
+}
+
+impl JoinRelation {
+
+    /// Build the hash table for the right (build) side, keyed by the evaluated join keys
+    fn build(&self, ctx: &ExecutionContext) -> Result<HashMap<Vec<Value>, Vec<Row>>, ExecutionError> {
+        let mut build_side: HashMap<Vec<Value>, Vec<Row>> = HashMap::new();
+
+        for t in self.right.scan(ctx) {
+            let tuple = t?;
+            let key = self.right_keys.iter()
+                .map(|e| ctx.evaluate(&tuple, self.right.schema(), e))
+                .collect::<Result<Vec<Value>, Box<ExecutionError>>>()
+                .map_err(|e| *e)?;
+            // SQL equality never holds between two NULLs, so a row with a null join key can't
+            // match anything; skip adding it to the build side rather than letting it hash-equal
+            // every other null-keyed row (e.g. from an optional foreign key column)
+            if JoinRelation::key_has_null(&key) {
+                continue;
+            }
+            build_side.entry(key).or_insert_with(Vec::new).push(tuple);
+        }
+
+        Ok(build_side)
+    }
+
+    fn key_has_null(key: &[Value]) -> bool {
+        key.iter().any(|v| *v == Value::Null)
+    }
+}
+
+impl SimpleRelation for JoinRelation {
+
+    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        let build_side = match self.build(ctx) {
+            Ok(b) => b,
+            Err(e) => return Box::new(vec![Err(e)].into_iter())
+        };
+
+        let left_keys = &self.left_keys;
+        let left_schema = self.left.schema();
+
+        let probe = self.left.scan(ctx).flat_map(move |t| {
+            let tuple = match t {
+                Ok(tuple) => tuple,
+                Err(e) => return vec![Err(e)]
+            };
+
+            let key = match left_keys.iter()
+                .map(|e| ctx.evaluate(&tuple, left_schema, e))
+                .collect::<Result<Vec<Value>, Box<ExecutionError>>>() {
+                Ok(k) => k,
+                Err(e) => return vec![Err(*e)]
+            };
+
+            // mirror the NULL short-circuit above: a null-keyed probe row never matches, even
+            // a null-keyed build row
+            if JoinRelation::key_has_null(&key) {
+                return vec![];
+            }
+
+            match build_side.get(&key) {
+                Some(matches) => matches.iter().map(|right_tuple| {
+                    let mut values = tuple.values.clone();
+                    values.extend(right_tuple.values.iter().cloned());
+                    Ok(Row::new(values))
+                }).collect(),
+                None => vec![]
+            }
+        });
+
+        Box::new(probe)
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
+/// One column of a multi-column `ORDER BY`
+#[derive(Clone, Debug)]
+pub struct SortExpr {
+    pub expr: Expr,
+    pub asc: bool,
+    pub nulls_first: bool
+}
+
+/// Orders `a` against `b`, honoring `nulls_first` ahead of `asc`: a `Value::Null` always sorts
+/// to the end named by `nulls_first`, regardless of sort direction, the way `NULLS FIRST` /
+/// `NULLS LAST` override `ASC`/`DESC` in SQL. Only when neither side is `Null` does `asc` decide
+/// the comparison.
+fn compare_sort_key(a: &Value, b: &Value, asc: bool, nulls_first: bool) -> Ordering {
+    match (*a == Value::Null, *b == Value::Null) {
+        (true, true) => Ordering::Equal,
+        (true, false) => if nulls_first { Ordering::Less } else { Ordering::Greater },
+        (false, true) => if nulls_first { Ordering::Greater } else { Ordering::Less },
+        (false, false) => {
+            let ord = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+            if asc { ord } else { ord.reverse() }
+        }
+    }
+}
+
+/// A single run produced while sorting: either a run spilled to a temp CSV file, or the
+/// final partial buffer kept in memory because it never grew past the spill threshold.
+enum RunReader {
+    Spilled { reader: csv::Reader<BufReader<File>>, schema: Schema, row: usize, path: String },
+    Memory(::std::vec::IntoIter<Row>)
+}
+
+impl RunReader {
+    fn next_row(&mut self) -> Option<Result<Row, ExecutionError>> {
+        match *self {
+            RunReader::Spilled { ref mut reader, ref schema, ref mut row, ref path } => {
+                let mut record = StringRecord::new();
+                match reader.read_record(&mut record) {
+                    Ok(true) => {
+                        let row_num = *row;
+                        *row += 1;
+                        Some(parse_record(schema, &record, row_num))
+                    },
+                    Ok(false) => {
+                        // the run is exhausted: its spilled temp file has served its purpose,
+                        // so clean it up rather than leaking one file per run into temp_dir
+                        let _ = ::std::fs::remove_file(path);
+                        None
+                    },
+                    Err(e) => Some(Err(ExecutionError::CsvError(e)))
+                }
+            },
+            RunReader::Memory(ref mut it) => it.next().map(Ok)
+        }
+    }
+}
+
+impl Drop for RunReader {
+    /// `next_row` only removes a run's temp file once that run is fully drained, but
+    /// `LimitRelation` (`ORDER BY ... LIMIT n`) stops polling `MergeIter` as soon as `n` rows
+    /// have come out, long before most runs of a multi-run spill are drained. Clean up here too
+    /// so an undrained run's file is still removed when the `RunReader` is dropped.
+    fn drop(&mut self) {
+        if let RunReader::Spilled { ref path, .. } = *self {
+            let _ = ::std::fs::remove_file(path);
+        }
+    }
+}
+
+/// One row waiting in the k-way merge heap, together with its already-evaluated sort key
+/// so the heap doesn't need to re-run `evaluate` on every comparison.
+struct HeapEntry {
+    key: Vec<Value>,
+    asc: Vec<bool>,
+    nulls_first: Vec<bool>,
+    row: Row,
+    run: usize
+}
+
+impl PartialEq for HeapEntry {
+    fn eq(&self, other: &Self) -> bool { self.key == other.key }
+}
+impl Eq for HeapEntry {}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        for i in 0..self.key.len() {
+            let ord = compare_sort_key(&self.key[i], &other.key[i], self.asc[i], self.nulls_first[i]);
+            if ord != Ordering::Equal {
+                // BinaryHeap is a max-heap but we want the smallest sort key out first
+                return ord.reverse();
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+/// Lazily merges the sorted runs produced by `SortRelation`, keeping only one buffered row
+/// per run resident via a `BinaryHeap`.
+struct MergeIter<'a> {
+    sort: &'a SortRelation,
+    ctx: &'a ExecutionContext,
+    runs: Vec<RunReader>,
+    heap: BinaryHeap<HeapEntry>,
+    /// an error hit while refilling the heap after a previous `next()` already committed to
+    /// yielding that call's row; surfaced on the following call instead, so a refill failure
+    /// doesn't discard the row that was already popped off the heap
+    pending_error: Option<ExecutionError>
+}
+
+impl<'a> MergeIter<'a> {
+    fn new(sort: &'a SortRelation, ctx: &'a ExecutionContext, mut runs: Vec<RunReader>) -> Self {
+        let mut heap = BinaryHeap::new();
+        for (i, run) in runs.iter_mut().enumerate() {
+            if let Some(Ok(row)) = run.next_row() {
+                if let Some(entry) = sort.heap_entry(ctx, row, i) {
+                    heap.push(entry);
+                }
+            }
+        }
+        MergeIter { sort, ctx, runs, heap, pending_error: None }
+    }
+}
+
+impl<'a> Iterator for MergeIter<'a> {
+    type Item = Result<Row, ExecutionError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(e) = self.pending_error.take() {
+            return Some(Err(e));
+        }
+
+        let entry = match self.heap.pop() {
+            Some(entry) => entry,
+            None => return None
+        };
+
+        if let Some(next) = self.runs[entry.run].next_row() {
+            match next {
+                Ok(row) => {
+                    if let Some(refilled) = self.sort.heap_entry(self.ctx, row, entry.run) {
+                        self.heap.push(refilled);
+                    }
+                },
+                // don't drop `entry.row` (already popped and owed to this call) on the floor;
+                // yield it now and surface the refill error on the next call instead
+                Err(e) => self.pending_error = Some(e)
+            }
+        }
+
+        Some(Ok(entry.row))
+    }
+}
+
+/// Implements `ORDER BY` as an external merge sort so the dataset doesn't need to fit in
+/// memory: rows are buffered until `ExecutionContext::sort_memory_limit` bytes are buffered,
+/// at which point the buffer is sorted and spilled to a temp file as a sorted run; the runs
+/// (plus the final in-memory buffer) are then merged lazily, one row per run at a time.
+pub struct SortRelation {
+    schema: Schema,
+    input: Box<SimpleRelation>,
+    sort_expr: Vec<SortExpr>
+}
+
+impl SortRelation {
+
+    fn compare(&self, ctx: &ExecutionContext, a: &Row, b: &Row) -> Ordering {
+        for se in &self.sort_expr {
+            let av = match ctx.evaluate(a, self.input.schema(), &se.expr) { Ok(v) => v, Err(_) => continue };
+            let bv = match ctx.evaluate(b, self.input.schema(), &se.expr) { Ok(v) => v, Err(_) => continue };
+            let ord = compare_sort_key(&av, &bv, se.asc, se.nulls_first);
+            if ord != Ordering::Equal {
+                return ord;
+            }
+        }
+        Ordering::Equal
+    }
+
+    fn heap_entry(&self, ctx: &ExecutionContext, row: Row, run: usize) -> Option<HeapEntry> {
+        let key = self.sort_expr.iter()
+            .map(|se| ctx.evaluate(&row, self.input.schema(), &se.expr))
+            .collect::<Result<Vec<Value>, Box<ExecutionError>>>();
+        match key {
+            Ok(key) => Some(HeapEntry {
+                key,
+                asc: self.sort_expr.iter().map(|se| se.asc).collect(),
+                nulls_first: self.sort_expr.iter().map(|se| se.nulls_first).collect(),
+                row,
+                run
+            }),
+            Err(_) => None
+        }
+    }
+
+    /// approximate in-memory footprint of a row, used to decide when to spill the buffer
+    fn row_size(row: &Row) -> usize {
+        row.values.iter().map(|v| match *v {
+            Value::String(ref s) => s.len(),
+            _ => 8
+        }).sum()
+    }
+
+    /// sort the buffer and flush it to a temp CSV file as a new run
+    fn spill(&self, ctx: &ExecutionContext, buffer: &mut Vec<Row>, run_id: usize) -> Result<String, ExecutionError> {
+        buffer.sort_by(|a, b| self.compare(ctx, a, b));
+
+        let path = format!("{}/datafusion-sort-{}-{}.csv", ::std::env::temp_dir().display(), process::id(), run_id);
+        let mut file = File::create(&path)?;
+        for row in buffer.iter() {
+            file.write(format!("{}\n", row.to_string()).as_bytes())?;
+        }
+        buffer.clear();
+
+        Ok(path)
+    }
+}
+
+impl SimpleRelation for SortRelation {
+
+    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        let mut buffer: Vec<Row> = Vec::new();
+        let mut buffered_bytes = 0usize;
+        let mut run_paths: Vec<String> = Vec::new();
+
+        for t in self.input.scan(ctx) {
+            let tuple = match t {
+                Ok(tuple) => tuple,
+                Err(e) => return Box::new(vec![Err(e)].into_iter())
+            };
+
+            buffered_bytes += SortRelation::row_size(&tuple);
+            buffer.push(tuple);
+
+            if buffered_bytes >= ctx.sort_memory_limit {
+                match self.spill(ctx, &mut buffer, run_paths.len()) {
+                    Ok(path) => run_paths.push(path),
+                    Err(e) => return Box::new(vec![Err(e)].into_iter())
+                }
+                buffered_bytes = 0;
+            }
+        }
+
+        buffer.sort_by(|a, b| self.compare(ctx, a, b));
+
+        if run_paths.is_empty() {
+            // nothing spilled: the in-memory buffer is already the fully sorted output
+            return Box::new(buffer.into_iter().map(Ok));
+        }
+
+        let mut runs: Vec<RunReader> = Vec::with_capacity(run_paths.len() + 1);
+        for path in &run_paths {
+            let file = match File::open(path) {
+                Ok(f) => f,
+                Err(e) => return Box::new(vec![Err(ExecutionError::from(e))].into_iter())
+            };
+            runs.push(RunReader::Spilled {
+                // a spilled run is written headerless (see `spill`), so unlike the reader used
+                // for a real CSV source this one must not treat its first row as a header
+                reader: csv::ReaderBuilder::new().has_headers(false).from_reader(BufReader::new(file)),
+                schema: self.input.schema().clone(),
+                row: 0,
+                path: path.clone()
+            });
+        }
+        runs.push(RunReader::Memory(buffer.into_iter()));
+
+        Box::new(MergeIter::new(self, ctx, runs))
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
+/// `true` if any node reachable from `plan` is a `NamedReference` to `name`, used to detect
+/// whether a `WITH RECURSIVE` term is actually self-referencing.
+fn plan_references(plan: &LogicalPlan, name: &str) -> bool {
+    match *plan {
+        LogicalPlan::NamedReference { name: ref n } => n == name,
+        LogicalPlan::Selection { ref input, .. } => plan_references(input, name),
+        LogicalPlan::Projection { ref input, .. } => plan_references(input, name),
+        LogicalPlan::Limit { ref input, .. } => plan_references(input, name),
+        LogicalPlan::Sort { ref input, .. } => plan_references(input, name),
+        LogicalPlan::Aggregate { ref input, .. } => plan_references(input, name),
+        LogicalPlan::Join { ref left, ref right, .. } => plan_references(left, name) || plan_references(right, name),
+        LogicalPlan::RecursiveQuery { ref anchor, ref recursive, .. } => plan_references(anchor, name) || plan_references(recursive, name),
+        LogicalPlan::CsvFile { .. } | LogicalPlan::TableScan { .. } | LogicalPlan::EmptyRelation => false
+    }
+}
+
+/// Resolves a table-qualified column (e.g. the `left` of `left.id`) to its index in the
+/// concatenated schema a `Join` produces, by walking down to the `TableScan` named `qualifier`
+/// and adding `offset` (the width of everything `JoinRelation` places before it: the left side,
+/// for any table reached through a `Join`'s right child). Returns `None` if `qualifier` names no
+/// table reachable from `plan`, or if it has no column called `unqualified`.
+fn resolve_qualified_column(plan: &LogicalPlan, qualifier: &str, unqualified: &str, offset: usize) -> Option<usize> {
+    match *plan {
+        LogicalPlan::TableScan { ref table_name, ref schema, .. } if table_name == qualifier =>
+            schema.column(unqualified).map(|(i, _)| offset + i),
+        LogicalPlan::Join { ref left, ref right, .. } =>
+            resolve_qualified_column(left, qualifier, unqualified, offset)
+                .or_else(|| resolve_qualified_column(right, qualifier, unqualified, offset + left.schema().columns.len())),
+        LogicalPlan::Selection { ref input, .. } => resolve_qualified_column(input, qualifier, unqualified, offset),
+        LogicalPlan::Projection { ref input, .. } => resolve_qualified_column(input, qualifier, unqualified, offset),
+        LogicalPlan::Limit { ref input, .. } => resolve_qualified_column(input, qualifier, unqualified, offset),
+        LogicalPlan::Sort { ref input, .. } => resolve_qualified_column(input, qualifier, unqualified, offset),
+        LogicalPlan::Aggregate { ref input, .. } => resolve_qualified_column(input, qualifier, unqualified, offset),
+        LogicalPlan::CsvFile { .. } | LogicalPlan::RecursiveQuery { .. } | LogicalPlan::NamedReference { .. } | LogicalPlan::EmptyRelation => None
+    }
+}
+
+/// Evaluates `WITH RECURSIVE <name> AS (<anchor> UNION ALL <recursive>)` as a fixed point:
+/// run the anchor once to seed the working set, then keep re-running the recursive term
+/// (with `name` resolved to the previous iteration's working set via a `NamedReference`)
+/// until an iteration produces no new rows.
+pub struct RecursiveRelation {
+    schema: Schema,
+    name: String,
+    anchor: Box<LogicalPlan>,
+    recursive: Box<LogicalPlan>
+}
+
+impl RecursiveRelation {
+
+    fn materialize(&self, ctx: &ExecutionContext) -> Result<Vec<Row>, ExecutionError> {
+        let anchor_rel = ctx.create_execution_plan(&self.anchor)?;
+        let anchor_rows = anchor_rel.scan(ctx).collect::<Result<Vec<Row>, ExecutionError>>()?;
+
+        if !plan_references(&self.recursive, &self.name) {
+            // the recursive term never reads the working set, so this isn't really
+            // recursive: every iteration would produce the same rows, so run it exactly once
+            let mut rows = anchor_rows;
+            let rel = ctx.create_execution_plan(&self.recursive)?;
+            rows.extend(rel.scan(ctx).collect::<Result<Vec<Row>, ExecutionError>>()?);
+            return Ok(rows);
+        }
+
+        let mut all_rows = anchor_rows.clone();
+        let mut working_set = anchor_rows;
+        let mut iterations = 0;
+
+        while !working_set.is_empty() {
+            iterations += 1;
+            if iterations > ctx.max_recursion_iterations {
+                return Err(ExecutionError::Custom(
+                    format!("WITH RECURSIVE \"{}\" exceeded the {}-iteration limit", self.name, ctx.max_recursion_iterations)));
+            }
+
+            let iter_ctx = ctx.with_named_relation(&self.name, self.schema.clone(), working_set);
+            let rel = iter_ctx.create_execution_plan(&self.recursive)?;
+            let next = rel.scan(&iter_ctx).collect::<Result<Vec<Row>, ExecutionError>>()?;
+
+            if next.is_empty() {
+                break;
+            }
+
+            all_rows.extend(next.iter().cloned());
+            working_set = next;
+        }
+
+        Ok(all_rows)
+    }
+}
+
+impl SimpleRelation for RecursiveRelation {
+
+    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
+        match self.materialize(ctx) {
+            Ok(rows) => Box::new(rows.into_iter().map(Ok)),
+            Err(e) => Box::new(vec![Err(e)].into_iter())
+        }
+    }
+
+    fn schema<'a>(&'a self) -> &'a Schema {
+        &self.schema
+    }
+}
+
+/// Converts a `LogicalPlan`/`Expr` tree to and from a portable, Substrait-style message so a
+/// coordinator can ship an `ExecutionPlan::Partition` body to a remote worker, which
+/// reconstructs the plan and runs it through `ExecutionContext::create_execution_plan`.
+///
+/// There is no protobuf dependency available in this crate yet, so the wire format below is
+/// a small length-prefixed, tagged binary encoding rather than real Substrait protobuf; each
+/// relational operator still maps onto the Substrait relation it corresponds to (Read /
+/// Filter / Project / Fetch), and each function is referenced by name the way a Substrait
+/// function extension would be.
+//TODO: replace this hand-rolled encoding with real Substrait protobuf once the crate depends
+// on `prost` and the `substrait` message definitions
+pub mod substrait {
+
+    use super::*;
+
+    struct Writer { buf: Vec<u8> }
+
+    impl Writer {
+        fn new() -> Self { Writer { buf: Vec::new() } }
+
+        fn u8(&mut self, v: u8) { self.buf.push(v); }
+
+        fn u32(&mut self, v: u32) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+
+        fn u64(&mut self, v: u64) { self.buf.extend_from_slice(&v.to_le_bytes()); }
+
+        fn f64(&mut self, v: f64) { self.buf.extend_from_slice(&v.to_bits().to_le_bytes()); }
+
+        fn bool(&mut self, v: bool) { self.u8(if v { 1 } else { 0 }); }
+
+        fn string(&mut self, s: &str) {
+            self.u32(s.len() as u32);
+            self.buf.extend_from_slice(s.as_bytes());
+        }
+    }
+
+    struct Reader<'a> { buf: &'a [u8], pos: usize }
+
+    impl<'a> Reader<'a> {
+        fn new(buf: &'a [u8]) -> Self { Reader { buf, pos: 0 } }
+
+        fn u8(&mut self) -> Result<u8, ExecutionError> {
+            let b = *self.buf.get(self.pos).ok_or_else(|| ExecutionError::Custom("unexpected end of substrait message".to_string()))?;
+            self.pos += 1;
+            Ok(b)
+        }
+
+        fn u32(&mut self) -> Result<u32, ExecutionError> {
+            let end = self.pos + 4;
+            let slice = self.buf.get(self.pos..end).ok_or_else(|| ExecutionError::Custom("unexpected end of substrait message".to_string()))?;
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(slice);
+            self.pos = end;
+            Ok(u32::from_le_bytes(bytes))
+        }
+
+        fn u64(&mut self) -> Result<u64, ExecutionError> {
+            let end = self.pos + 8;
+            let slice = self.buf.get(self.pos..end).ok_or_else(|| ExecutionError::Custom("unexpected end of substrait message".to_string()))?;
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(slice);
+            self.pos = end;
+            Ok(u64::from_le_bytes(bytes))
+        }
+
+        fn f64(&mut self) -> Result<f64, ExecutionError> {
+            Ok(f64::from_bits(self.u64()?))
+        }
+
+        fn bool(&mut self) -> Result<bool, ExecutionError> {
+            Ok(self.u8()? != 0)
+        }
+
+        fn string(&mut self) -> Result<String, ExecutionError> {
+            let len = self.u32()? as usize;
+            let end = self.pos + len;
+            let slice = self.buf.get(self.pos..end).ok_or_else(|| ExecutionError::Custom("unexpected end of substrait message".to_string()))?;
+            self.pos = end;
+            String::from_utf8(slice.to_vec()).map_err(|e| ExecutionError::Custom(e.to_string()))
+        }
+    }
+
+    fn write_data_type(w: &mut Writer, dt: &DataType) -> Result<(), ExecutionError> {
+        match *dt {
+            DataType::UnsignedLong => w.u8(0),
+            DataType::String => w.u8(1),
+            DataType::Double => w.u8(2),
+            DataType::Boolean => w.u8(3),
+            _ => return Err(ExecutionError::Custom("unsupported DataType in substrait encoding".to_string()))
+        }
+        Ok(())
+    }
+
+    fn read_data_type(r: &mut Reader) -> Result<DataType, ExecutionError> {
+        match r.u8()? {
+            0 => Ok(DataType::UnsignedLong),
+            1 => Ok(DataType::String),
+            2 => Ok(DataType::Double),
+            3 => Ok(DataType::Boolean),
+            t => Err(ExecutionError::Custom(format!("unknown substrait DataType tag {}", t)))
+        }
+    }
+
+    fn write_schema(w: &mut Writer, schema: &Schema) -> Result<(), ExecutionError> {
+        w.u32(schema.columns.len() as u32);
+        for field in &schema.columns {
+            w.string(&field.name);
+            write_data_type(w, &field.data_type)?;
+            w.bool(field.nullable);
+        }
+        Ok(())
+    }
+
+    fn read_schema(r: &mut Reader) -> Result<Schema, ExecutionError> {
+        let n = r.u32()?;
+        let mut columns = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            let name = r.string()?;
+            let data_type = read_data_type(r)?;
+            let nullable = r.bool()?;
+            columns.push(Field::new(&name, data_type, nullable));
+        }
+        Ok(Schema { columns })
+    }
+
+    fn write_value(w: &mut Writer, value: &Value) -> Result<(), ExecutionError> {
+        match *value {
+            Value::UnsignedLong(n) => { w.u8(0); w.u64(n); },
+            Value::String(ref s) => { w.u8(1); w.string(s); },
+            Value::Double(n) => { w.u8(2); w.f64(n); },
+            Value::Boolean(b) => { w.u8(3); w.bool(b); },
+            _ => return Err(ExecutionError::Custom("unsupported Value in substrait encoding".to_string()))
+        }
+        Ok(())
+    }
+
+    fn read_value(r: &mut Reader) -> Result<Value, ExecutionError> {
+        match r.u8()? {
+            0 => Ok(Value::UnsignedLong(r.u64()?)),
+            1 => Ok(Value::String(r.string()?)),
+            2 => Ok(Value::Double(r.f64()?)),
+            3 => Ok(Value::Boolean(r.bool()?)),
+            t => Err(ExecutionError::Custom(format!("unknown substrait Value tag {}", t)))
+        }
+    }
+
+    fn write_operator(w: &mut Writer, op: &Operator) {
+        w.u8(match *op {
+            Operator::Eq => 0,
+            Operator::NotEq => 1,
+            Operator::Lt => 2,
+            Operator::LtEq => 3,
+            Operator::Gt => 4,
+            Operator::GtEq => 5
+        });
+    }
+
+    fn read_operator(r: &mut Reader) -> Result<Operator, ExecutionError> {
+        match r.u8()? {
+            0 => Ok(Operator::Eq),
+            1 => Ok(Operator::NotEq),
+            2 => Ok(Operator::Lt),
+            3 => Ok(Operator::LtEq),
+            4 => Ok(Operator::Gt),
+            5 => Ok(Operator::GtEq),
+            t => Err(ExecutionError::Custom(format!("unknown substrait Operator tag {}", t)))
+        }
+    }
 
-    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
-        Box::new(self.input.scan(ctx).filter(move|t|
-            match t {
-                &Ok(ref tuple) => match ctx.evaluate(tuple, &self.schema, &self.expr) {
-                    Ok(Value::Boolean(b)) => b,
-                    _ => panic!("Predicate expression evaluated to non-boolean value")
-                },
-                _ => true // let errors through the filter so they can be handled later
+    /// Each scalar/aggregate function is referenced by name, the way a Substrait function
+    /// extension is referenced by an anchor into the plan's extension list.
+    fn write_expr(w: &mut Writer, expr: &Expr) -> Result<(), ExecutionError> {
+        match *expr {
+            Expr::BinaryExpr { ref left, ref op, ref right } => {
+                w.u8(0);
+                write_expr(w, left)?;
+                write_operator(w, op);
+                write_expr(w, right)?;
+            },
+            Expr::TupleValue(i) => {
+                w.u8(1);
+                w.u32(i as u32);
+            },
+            Expr::Literal(ref value) => {
+                w.u8(2);
+                write_value(w, value)?;
+            },
+            Expr::ScalarFunction { ref name, ref args } => {
+                w.u8(3);
+                w.string(name);
+                w.u32(args.len() as u32);
+                for a in args {
+                    write_expr(w, a)?;
+                }
             }
-        ))
+        }
+        Ok(())
     }
 
-    fn schema<'a>(&'a self) -> &'a Schema {
-        &self.schema
+    fn read_expr(r: &mut Reader) -> Result<Expr, ExecutionError> {
+        match r.u8()? {
+            0 => {
+                let left = Box::new(read_expr(r)?);
+                let op = read_operator(r)?;
+                let right = Box::new(read_expr(r)?);
+                Ok(Expr::BinaryExpr { left, op, right })
+            },
+            1 => Ok(Expr::TupleValue(r.u32()? as usize)),
+            2 => Ok(Expr::Literal(read_value(r)?)),
+            3 => {
+                let name = r.string()?;
+                let n = r.u32()?;
+                let mut args = Vec::with_capacity(n as usize);
+                for _ in 0..n {
+                    args.push(read_expr(r)?);
+                }
+                Ok(Expr::ScalarFunction { name, args })
+            },
+            t => Err(ExecutionError::Custom(format!("unknown substrait Expr tag {}", t)))
+        }
     }
-}
 
-impl SimpleRelation for ProjectRelation {
+    fn write_exprs(w: &mut Writer, exprs: &[Expr]) -> Result<(), ExecutionError> {
+        w.u32(exprs.len() as u32);
+        for e in exprs {
+            write_expr(w, e)?;
+        }
+        Ok(())
+    }
 
-    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
-        let foo = self.input.scan(ctx).map(move|r| match r {
-            Ok(tuple) => {
-                let values = self.expr.iter()
-                    .map(|e| match e {
-                        &Expr::TupleValue(i) => tuple.values[i].clone(),
-                        //TODO: relation delegating back to execution context seems wrong way around
-                        _ => ctx.evaluate(&tuple,&self.schema, e).unwrap() //TODO: remove unwrap
-                        //unimplemented!("Unsupported expression for projection")
-                    })
-                    .collect();
-                Ok(Row::new(values))
-            },
-            Err(_) => r
-        });
+    fn read_exprs(r: &mut Reader) -> Result<Vec<Expr>, ExecutionError> {
+        let n = r.u32()?;
+        let mut exprs = Vec::with_capacity(n as usize);
+        for _ in 0..n {
+            exprs.push(read_expr(r)?);
+        }
+        Ok(exprs)
+    }
 
-        Box::new(foo)
+    /// Relation tags: 0=Read(CsvFile) 1=Read(TableScan) 2=Filter 3=Project 4=Fetch(Limit)
+    /// 5=Join 6=Aggregate
+    fn write_plan(w: &mut Writer, plan: &LogicalPlan) -> Result<(), ExecutionError> {
+        match *plan {
+            LogicalPlan::CsvFile { ref filename, ref schema } => {
+                w.u8(0);
+                w.string(filename);
+                write_schema(w, schema)?;
+            },
+            LogicalPlan::TableScan { ref schema_name, ref table_name, ref schema } => {
+                w.u8(1);
+                w.string(schema_name);
+                w.string(table_name);
+                write_schema(w, schema)?;
+            },
+            LogicalPlan::Selection { ref expr, ref input, ref schema } => {
+                w.u8(2);
+                write_expr(w, expr)?;
+                write_plan(w, input)?;
+                write_schema(w, schema)?;
+            },
+            LogicalPlan::Projection { ref expr, ref input, ref schema } => {
+                w.u8(3);
+                write_exprs(w, expr)?;
+                write_plan(w, input)?;
+                write_schema(w, schema)?;
+            },
+            LogicalPlan::Limit { limit, ref input, ref schema, .. } => {
+                w.u8(4);
+                w.u64(limit as u64);
+                write_plan(w, input)?;
+                write_schema(w, schema)?;
+            },
+            LogicalPlan::Join { ref left, ref right, ref left_keys, ref right_keys, ref schema } => {
+                w.u8(5);
+                write_plan(w, left)?;
+                write_plan(w, right)?;
+                write_exprs(w, left_keys)?;
+                write_exprs(w, right_keys)?;
+                write_schema(w, schema)?;
+            },
+            LogicalPlan::Aggregate { ref group_expr, ref aggr_expr, ref input, ref schema } => {
+                w.u8(6);
+                write_exprs(w, group_expr)?;
+                write_exprs(w, aggr_expr)?;
+                write_plan(w, input)?;
+                write_schema(w, schema)?;
+            },
+            LogicalPlan::EmptyRelation => {
+                return Err(ExecutionError::Custom("EmptyRelation cannot be serialized to substrait".to_string()));
+            },
+            LogicalPlan::Sort { .. } => {
+                return Err(ExecutionError::Custom("Sort is not yet representable in substrait".to_string()));
+            },
+            LogicalPlan::RecursiveQuery { .. } => {
+                return Err(ExecutionError::Custom("WITH RECURSIVE is not yet representable in substrait".to_string()));
+            },
+            LogicalPlan::NamedReference { .. } => {
+                return Err(ExecutionError::Custom("a recursive working-set reference is not yet representable in substrait".to_string()));
+            }
+        }
+        Ok(())
     }
 
-    fn schema<'a>(&'a self) -> &'a Schema {
-        &self.schema
+    fn read_plan(r: &mut Reader) -> Result<LogicalPlan, ExecutionError> {
+        match r.u8()? {
+            0 => {
+                let filename = r.string()?;
+                let schema = read_schema(r)?;
+                Ok(LogicalPlan::CsvFile { filename, schema })
+            },
+            1 => {
+                let schema_name = r.string()?;
+                let table_name = r.string()?;
+                let schema = read_schema(r)?;
+                Ok(LogicalPlan::TableScan { schema_name, table_name, schema })
+            },
+            2 => {
+                let expr = read_expr(r)?;
+                let input = Box::new(read_plan(r)?);
+                let schema = read_schema(r)?;
+                Ok(LogicalPlan::Selection { expr, input, schema })
+            },
+            3 => {
+                let expr = read_exprs(r)?;
+                let input = Box::new(read_plan(r)?);
+                let schema = read_schema(r)?;
+                Ok(LogicalPlan::Projection { expr, input, schema })
+            },
+            4 => {
+                let limit = r.u64()? as usize;
+                let input = Box::new(read_plan(r)?);
+                let schema = read_schema(r)?;
+                Ok(LogicalPlan::Limit { limit, input, schema })
+            },
+            5 => {
+                let left = Box::new(read_plan(r)?);
+                let right = Box::new(read_plan(r)?);
+                let left_keys = read_exprs(r)?;
+                let right_keys = read_exprs(r)?;
+                let schema = read_schema(r)?;
+                Ok(LogicalPlan::Join { left, right, left_keys, right_keys, schema })
+            },
+            6 => {
+                let group_expr = read_exprs(r)?;
+                let aggr_expr = read_exprs(r)?;
+                let input = Box::new(read_plan(r)?);
+                let schema = read_schema(r)?;
+                Ok(LogicalPlan::Aggregate { group_expr, aggr_expr, input, schema })
+            },
+            t => Err(ExecutionError::Custom(format!("unknown substrait relation tag {}", t)))
+        }
     }
-}
 
-impl SimpleRelation for LimitRelation {
-    fn scan<'a>(&'a self, ctx: &'a ExecutionContext) -> Box<Iterator<Item=Result<Row, ExecutionError>> + 'a> {
-        Box::new(self.input.scan(ctx).take(self.limit))
+    pub fn plan_to_bytes(plan: &LogicalPlan) -> Result<Vec<u8>, ExecutionError> {
+        let mut w = Writer::new();
+        write_plan(&mut w, plan)?;
+        Ok(w.buf)
     }
 
-    fn schema<'a>(&'a self) -> &'a Schema {
-        &self.schema
+    pub fn bytes_to_plan(bytes: &[u8]) -> Result<LogicalPlan, ExecutionError> {
+        let mut r = Reader::new(bytes);
+        read_plan(&mut r)
     }
 }
 
@@ -200,10 +1268,23 @@ pub enum ExecutionPlan {
 }
 
 
+/// Default spill threshold for `SortRelation`'s external merge sort: buffer up to 64MB of
+/// row data before sorting the buffer and flushing it to a temp file as a run.
+const DEFAULT_SORT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// Default guard on `RecursiveRelation`'s fixed-point loop, in case a recursive query never
+/// reaches a fixed point.
+const DEFAULT_MAX_RECURSION_ITERATIONS: usize = 100;
+
 #[derive(Debug,Clone)]
 pub struct ExecutionContext {
     schemas: HashMap<String, Schema>,
     functions: HashMap<String, FunctionMeta>,
+    sort_memory_limit: usize,
+    max_recursion_iterations: usize,
+    /// working sets registered for in-flight recursive queries, keyed by CTE name; consulted
+    /// when resolving a `LogicalPlan::NamedReference`
+    named_relations: HashMap<String, (Schema, Vec<Row>)>,
 
 }
 
@@ -212,7 +1293,10 @@ impl ExecutionContext {
     pub fn new() -> Self {
         ExecutionContext {
             schemas: HashMap::new(),
-            functions: HashMap::new()
+            functions: HashMap::new(),
+            sort_memory_limit: DEFAULT_SORT_MEMORY_LIMIT,
+            max_recursion_iterations: DEFAULT_MAX_RECURSION_ITERATIONS,
+            named_relations: HashMap::new()
         }
     }
 
@@ -220,6 +1304,24 @@ impl ExecutionContext {
         self.schemas.insert(name.to_string(), schema.clone());
     }
 
+    /// tune the number of bytes of row data `SortRelation` buffers before spilling a run to disk
+    pub fn set_sort_memory_limit(&mut self, bytes: usize) {
+        self.sort_memory_limit = bytes;
+    }
+
+    /// tune the fixed-point iteration guard used by `RecursiveRelation`
+    pub fn set_max_recursion_iterations(&mut self, n: usize) {
+        self.max_recursion_iterations = n;
+    }
+
+    /// clone this context with `name` bound to the working set produced by the previous
+    /// iteration of a recursive query, so `LogicalPlan::NamedReference { name }` resolves to it
+    fn with_named_relation(&self, name: &str, schema: Schema, rows: Vec<Row>) -> Self {
+        let mut ctx = self.clone();
+        ctx.named_relations.insert(name.to_string(), (schema, rows));
+        ctx
+    }
+
     pub fn define_function(&mut self, func: &ScalarFunction) {
 
         let fm = FunctionMeta {
@@ -325,6 +1427,65 @@ impl ExecutionContext {
                 };
                 Ok(Box::new(rel))
             }
+
+            //TODO: `sqltorel.rs` is not part of this checkout, so `SqlToRel` still does not
+            // parse `GROUP BY` / aggregate functions into this variant — until that wiring
+            // lands, `LogicalPlan::Aggregate` is only reachable by building a `LogicalPlan` by
+            // hand, not from SQL
+            LogicalPlan::Aggregate { ref group_expr, ref aggr_expr, ref input, ref schema } => {
+                let input_rel = self.create_execution_plan(input)?;
+                let rel = AggregateRelation {
+                    input: input_rel,
+                    group_expr: group_expr.clone(),
+                    aggr_expr: aggr_expr.clone(),
+                    schema: schema.clone()
+                };
+                Ok(Box::new(rel))
+            }
+
+            LogicalPlan::Join { ref left, ref right, ref left_keys, ref right_keys, ref schema } => {
+                let left_rel = self.create_execution_plan(left)?;
+                let right_rel = self.create_execution_plan(right)?;
+                let rel = JoinRelation {
+                    left: left_rel,
+                    right: right_rel,
+                    left_keys: left_keys.clone(),
+                    right_keys: right_keys.clone(),
+                    schema: schema.clone()
+                };
+                Ok(Box::new(rel))
+            }
+
+            LogicalPlan::Sort { ref sort_expr, ref input, ref schema } => {
+                let input_rel = self.create_execution_plan(input)?;
+                let rel = SortRelation {
+                    input: input_rel,
+                    sort_expr: sort_expr.clone(),
+                    schema: schema.clone()
+                };
+                Ok(Box::new(rel))
+            }
+
+            //TODO: `sqltorel.rs`/`parser.rs` (not part of this checkout) still don't parse
+            // `WITH RECURSIVE <name> AS (...)` into this variant — until that wiring lands,
+            // `LogicalPlan::RecursiveQuery` is only reachable by building a `LogicalPlan` by
+            // hand, not from SQL
+            LogicalPlan::RecursiveQuery { ref name, ref anchor, ref recursive, ref schema } => {
+                let rel = RecursiveRelation {
+                    name: name.clone(),
+                    anchor: anchor.clone(),
+                    recursive: recursive.clone(),
+                    schema: schema.clone()
+                };
+                Ok(Box::new(rel))
+            }
+
+            LogicalPlan::NamedReference { ref name } => {
+                match self.named_relations.get(name) {
+                    Some(&(ref schema, ref rows)) => Ok(Box::new(InMemoryRelation { schema: schema.clone(), rows: rows.clone() })),
+                    None => Err(ExecutionError::Custom(format!("no working set registered for recursive reference \"{}\"", name)))
+                }
+            }
         }
     }
 
@@ -335,6 +1496,14 @@ impl ExecutionContext {
             &Expr::BinaryExpr { ref left, ref op, ref right } => {
                 let left_value = self.evaluate(tuple, tt, left)?;
                 let right_value = self.evaluate(tuple, tt, right)?;
+                // SQL three-valued logic, simplified: a comparison against NULL is neither
+                // true nor false, but this engine has no tri-state boolean, so treat it as
+                // false rather than panicking trying to order/equate a Value::Null with anything
+                // (a predicate that itself evaluates to Null is still a non-boolean error in
+                // `FilterRelation`)
+                if left_value == Value::Null || right_value == Value::Null {
+                    return Ok(Value::Boolean(false));
+                }
                 match op {
                     &Operator::Eq => Ok(Value::Boolean(left_value == right_value)),
                     &Operator::NotEq => Ok(Value::Boolean(left_value != right_value)),
@@ -382,6 +1551,18 @@ impl ExecutionContext {
         Expr::ScalarFunction { name: name.to_string(), args: args.clone() }
     }
 
+    /// Serialize a logical plan so it can be shipped to a remote worker as the body of an
+    /// `ExecutionPlan::Partition`
+    pub fn plan_to_substrait(&self, plan: &LogicalPlan) -> Result<Vec<u8>, ExecutionError> {
+        substrait::plan_to_bytes(plan)
+    }
+
+    /// Reconstruct a logical plan received from a coordinator so it can be run through
+    /// `create_execution_plan`
+    pub fn substrait_to_plan(&self, bytes: &[u8]) -> Result<LogicalPlan, ExecutionError> {
+        substrait::bytes_to_plan(bytes)
+    }
+
 }
 
 
@@ -440,10 +1621,47 @@ impl DataFrame for DF {
         Ok(())
     }
 
+    fn collect(&self) -> Result<Vec<Row>, DataFrameError> {
+        let execution_plan = self.ctx.create_execution_plan(&self.plan)?;
+        let rows = execution_plan.scan(&self.ctx)
+            .collect::<Result<Vec<Row>, ExecutionError>>()?;
+        Ok(rows)
+    }
+
+    fn count(&self) -> Result<usize, DataFrameError> {
+        Ok(self.collect()?.len())
+    }
+
+    fn take(&self, n: usize) -> Result<Vec<Row>, DataFrameError> {
+        let plan = LogicalPlan::Limit {
+            limit: n,
+            input: self.plan.clone(),
+            schema: self.plan.schema().clone()
+        };
+
+        DF { ctx: self.ctx.clone(), plan: Box::new(plan) }.collect()
+    }
+
     fn col(&self, column_name: &str) -> Result<Expr, DataFrameError> {
-        match self.plan.schema().column(column_name) {
-            Some((i,_)) => Ok(Expr::TupleValue(i)),
-            _ => Err(DataFrameError::InvalidColumn(column_name.to_string()))
+        // a join's output schema concatenates both input schemas, so a qualified name like
+        // "t.col" is resolved against the join tree via `resolve_qualified_column` rather than
+        // by discarding the qualifier and looking up the bare column name, which would return
+        // whichever side's column happened to come first whenever both sides share a name
+        //TODO: SqlToRel (in sqltorel.rs, not part of this checkout) still needs to emit
+        // qualified column names like "left.id" for this path to be reachable from SQL
+        match column_name.rfind('.') {
+            Some(i) => {
+                let qualifier = &column_name[..i];
+                let unqualified = &column_name[i + 1..];
+                match resolve_qualified_column(&self.plan, qualifier, unqualified, 0) {
+                    Some(index) => Ok(Expr::TupleValue(index)),
+                    None => Err(DataFrameError::InvalidColumn(column_name.to_string()))
+                }
+            },
+            None => match self.plan.schema().column(column_name) {
+                Some((i,_)) => Ok(Expr::TupleValue(i)),
+                _ => Err(DataFrameError::InvalidColumn(column_name.to_string()))
+            }
         }
     }
 
@@ -460,6 +1678,447 @@ impl DataFrame for DF {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_aggregate_min_max_over_all_null_group_is_null_not_panic() {
+        // every row in group "b" has a null value, so its Min/Max accumulators never see a
+        // non-null input; `finish()` used to `.expect()` on that and panic the whole scan
+        let input = InMemoryRelation {
+            schema: Schema::new(vec![
+                Field::new("category", DataType::String, false),
+                Field::new("value", DataType::Double, true)]),
+            rows: vec![
+                Row::new(vec![Value::String("a".to_string()), Value::Double(10.0)]),
+                Row::new(vec![Value::String("a".to_string()), Value::Null]),
+                Row::new(vec![Value::String("b".to_string()), Value::Null]),
+                Row::new(vec![Value::String("b".to_string()), Value::Null])]
+        };
+
+        let agg = AggregateRelation {
+            schema: Schema::new(vec![
+                Field::new("category", DataType::String, false),
+                Field::new("min_value", DataType::Double, true),
+                Field::new("sum_value", DataType::Double, false)]),
+            input: Box::new(input),
+            group_expr: vec![Expr::TupleValue(0)],
+            aggr_expr: vec![
+                Expr::ScalarFunction { name: "min".to_string(), args: vec![Expr::TupleValue(1)] },
+                Expr::ScalarFunction { name: "sum".to_string(), args: vec![Expr::TupleValue(1)] }]
+        };
+
+        let ctx = ExecutionContext::new();
+        let rows = agg.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+        assert_eq!(rows.len(), 2);
+
+        let a_row = rows.iter().find(|r| r.values[0] == Value::String("a".to_string())).unwrap();
+        assert_eq!(a_row.values[1], Value::Double(10.0));
+        assert_eq!(a_row.values[2], Value::Double(10.0));
+
+        let b_row = rows.iter().find(|r| r.values[0] == Value::String("b".to_string())).unwrap();
+        assert_eq!(b_row.values[1], Value::Null);
+        assert_eq!(b_row.values[2], Value::Null);
+    }
+
+    #[test]
+    fn test_aggregate_materialize_surfaces_unsupported_function_as_error_not_panic() {
+        let input = InMemoryRelation {
+            schema: Schema::new(vec![Field::new("value", DataType::Double, false)]),
+            rows: vec![Row::new(vec![Value::Double(1.0)])]
+        };
+
+        let agg = AggregateRelation {
+            schema: Schema::new(vec![Field::new("bogus_value", DataType::Double, false)]),
+            input: Box::new(input),
+            group_expr: vec![],
+            aggr_expr: vec![Expr::ScalarFunction { name: "bogus".to_string(), args: vec![Expr::TupleValue(0)] }]
+        };
+
+        let ctx = ExecutionContext::new();
+        let result = agg.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>();
+        match result {
+            Err(ExecutionError::Custom(msg)) => assert!(msg.contains("bogus")),
+            other => panic!("expected an unsupported-aggregate-function error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_aggregate_global_empty_input_seeds_single_row() {
+        let input = InMemoryRelation {
+            schema: Schema::new(vec![Field::new("id", DataType::UnsignedLong, false)]),
+            rows: vec![]
+        };
+
+        let agg = AggregateRelation {
+            schema: Schema::new(vec![Field::new("count", DataType::UnsignedLong, false)]),
+            input: Box::new(input),
+            group_expr: vec![],
+            aggr_expr: vec![Expr::ScalarFunction { name: "count".to_string(), args: vec![Expr::TupleValue(0)] }]
+        };
+
+        let ctx = ExecutionContext::new();
+        let rows = agg.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values[0], Value::UnsignedLong(0));
+    }
+
+    /// Writes a single-column, one-row CSV fixture (with header) for the `RecursiveRelation`
+    /// tests below, since `LogicalPlan::CsvFile`/`TableScan` read real files rather than an
+    /// in-memory relation
+    fn write_single_row_csv(name_suffix: &str) -> String {
+        let path = format!("{}/datafusion-test-recursive-{}.csv", ::std::env::temp_dir().display(), name_suffix);
+        let mut file = File::create(&path).unwrap();
+        file.write(b"n\n1\n").unwrap();
+        path
+    }
+
+    #[test]
+    fn test_recursive_relation_reaches_a_fixed_point() {
+        let schema = Schema::new(vec![Field::new("n", DataType::UnsignedLong, false)]);
+        let anchor_path = write_single_row_csv("fixed-point");
+
+        // the recursive term's predicate is never true, so the working set goes empty after
+        // the anchor's one row, and the fixed point is just the anchor's output
+        let recursive = LogicalPlan::Selection {
+            expr: Expr::BinaryExpr {
+                left: Box::new(Expr::TupleValue(0)),
+                op: Operator::NotEq,
+                right: Box::new(Expr::TupleValue(0))
+            },
+            input: Box::new(LogicalPlan::NamedReference { name: "t".to_string() }),
+            schema: schema.clone()
+        };
+
+        let rel = RecursiveRelation {
+            schema: schema.clone(),
+            name: "t".to_string(),
+            anchor: Box::new(LogicalPlan::CsvFile { filename: anchor_path, schema: schema.clone() }),
+            recursive: Box::new(recursive)
+        };
+
+        let ctx = ExecutionContext::new();
+        let rows = rel.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values[0], Value::UnsignedLong(1));
+    }
+
+    #[test]
+    fn test_recursive_relation_falls_back_to_a_single_pass_when_not_self_referencing() {
+        let schema = Schema::new(vec![Field::new("n", DataType::UnsignedLong, false)]);
+        let anchor_path = write_single_row_csv("non-recursive");
+
+        // the recursive term never reads the working set via a NamedReference, so this isn't
+        // really recursive: it should run exactly once rather than looping to a fixed point
+        let recursive = LogicalPlan::CsvFile { filename: anchor_path.clone(), schema: schema.clone() };
+
+        let rel = RecursiveRelation {
+            schema: schema.clone(),
+            name: "t".to_string(),
+            anchor: Box::new(LogicalPlan::CsvFile { filename: anchor_path, schema: schema.clone() }),
+            recursive: Box::new(recursive)
+        };
+
+        let ctx = ExecutionContext::new();
+        let rows = rel.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn test_recursive_relation_errors_past_the_iteration_limit() {
+        let schema = Schema::new(vec![Field::new("n", DataType::UnsignedLong, false)]);
+        let anchor_path = write_single_row_csv("iteration-limit");
+
+        // the recursive term re-emits the working set unchanged, so it never reaches a fixed
+        // point and should be cut off by max_recursion_iterations rather than looping forever
+        let recursive = LogicalPlan::Projection {
+            expr: vec![Expr::TupleValue(0)],
+            input: Box::new(LogicalPlan::NamedReference { name: "t".to_string() }),
+            schema: schema.clone()
+        };
+
+        let rel = RecursiveRelation {
+            schema: schema.clone(),
+            name: "t".to_string(),
+            anchor: Box::new(LogicalPlan::CsvFile { filename: anchor_path, schema: schema.clone() }),
+            recursive: Box::new(recursive)
+        };
+
+        let mut ctx = ExecutionContext::new();
+        ctx.set_max_recursion_iterations(3);
+
+        let result = rel.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>();
+        match result {
+            Err(ExecutionError::Custom(msg)) => assert!(msg.contains("exceeded")),
+            other => panic!("expected an iteration-limit error, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_sort_spills_multiple_runs_and_merges_in_order() {
+        let mut ctx = ExecutionContext::new();
+        // each Double row is 8 bytes (see SortRelation::row_size), so this spills a new run
+        // every 2 rows buffered
+        ctx.set_sort_memory_limit(16);
+
+        let input = InMemoryRelation {
+            schema: Schema::new(vec![Field::new("value", DataType::Double, true)]),
+            rows: vec![
+                Row::new(vec![Value::Double(5.0)]),
+                Row::new(vec![Value::Double(1.0)]),
+                Row::new(vec![Value::Null]),
+                Row::new(vec![Value::Double(3.0)]),
+                Row::new(vec![Value::Double(2.0)])]
+        };
+        let schema = input.schema().clone();
+
+        let sort = SortRelation {
+            schema,
+            input: Box::new(input),
+            sort_expr: vec![SortExpr { expr: Expr::TupleValue(0), asc: true, nulls_first: false }]
+        };
+
+        let rows = sort.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+        let values: Vec<Value> = rows.into_iter().map(|r| r.values[0].clone()).collect();
+        assert_eq!(values, vec![
+            Value::Double(1.0), Value::Double(2.0), Value::Double(3.0), Value::Double(5.0), Value::Null]);
+    }
+
+    #[test]
+    fn test_sort_with_limit_does_not_leak_spilled_run_files() {
+        let mut ctx = ExecutionContext::new();
+        ctx.set_sort_memory_limit(16);
+
+        let input = InMemoryRelation {
+            schema: Schema::new(vec![Field::new("value", DataType::Double, false)]),
+            rows: (0..10).rev().map(|n| Row::new(vec![Value::Double(n as f64)])).collect()
+        };
+        let schema = input.schema().clone();
+
+        let sort = SortRelation {
+            schema: schema.clone(),
+            input: Box::new(input),
+            sort_expr: vec![SortExpr { expr: Expr::TupleValue(0), asc: true, nulls_first: true }]
+        };
+
+        let limit = LimitRelation {
+            schema,
+            input: Box::new(sort),
+            limit: 2
+        };
+
+        // scoped so the boxed iterator (and the MergeIter/RunReaders it owns) is dropped before
+        // checking for leftover temp files, mirroring a caller that only reads the first `n` rows
+        {
+            let rows = limit.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+            assert_eq!(rows.len(), 2);
+        }
+
+        let prefix = format!("datafusion-sort-{}-", process::id());
+        let leftover = ::std::fs::read_dir(::std::env::temp_dir()).unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+            .count();
+        assert_eq!(leftover, 0);
+    }
+
+    #[test]
+    fn test_merge_iter_yields_a_popped_row_before_surfacing_its_refill_error() {
+        let schema = Schema::new(vec![Field::new("value", DataType::UnsignedLong, false)]);
+
+        // handcrafted so the first record parses fine but the second does not, so the
+        // refill triggered by draining the first record fails
+        let path = format!("{}/datafusion-merge-iter-test-{}.csv", ::std::env::temp_dir().display(), process::id());
+        {
+            let mut file = File::create(&path).unwrap();
+            file.write(b"1\nnot-a-number\n").unwrap();
+        }
+
+        let spilled = RunReader::Spilled {
+            reader: csv::ReaderBuilder::new().has_headers(false).from_reader(BufReader::new(File::open(&path).unwrap())),
+            schema: schema.clone(),
+            row: 0,
+            path: path.clone()
+        };
+        let memory = RunReader::Memory(vec![Row::new(vec![Value::UnsignedLong(5)])].into_iter());
+
+        let sort = SortRelation {
+            schema: schema.clone(),
+            input: Box::new(InMemoryRelation { schema, rows: vec![] }),
+            sort_expr: vec![SortExpr { expr: Expr::TupleValue(0), asc: true, nulls_first: false }]
+        };
+        let ctx = ExecutionContext::new();
+
+        let mut merge = MergeIter::new(&sort, &ctx, vec![spilled, memory]);
+
+        // the row popped off the heap ahead of the failed refill must still come out...
+        assert_eq!(merge.next().unwrap().unwrap().values, vec![Value::UnsignedLong(1)]);
+        // ...with the refill error surfacing on the following call instead of being dropped...
+        assert!(merge.next().unwrap().is_err());
+        // ...and the other run keeps draining normally afterwards
+        assert_eq!(merge.next().unwrap().unwrap().values, vec![Value::UnsignedLong(5)]);
+        assert!(merge.next().is_none());
+    }
+
+    #[test]
+    fn test_join_matches_and_non_matches() {
+        let left = InMemoryRelation {
+            schema: Schema::new(vec![
+                Field::new("id", DataType::UnsignedLong, false),
+                Field::new("name", DataType::String, false)]),
+            rows: vec![
+                Row::new(vec![Value::UnsignedLong(1), Value::String("alice".to_string())]),
+                Row::new(vec![Value::UnsignedLong(2), Value::String("bob".to_string())])]
+        };
+        let right = InMemoryRelation {
+            schema: Schema::new(vec![
+                Field::new("id", DataType::UnsignedLong, false),
+                Field::new("amount", DataType::Double, false)]),
+            rows: vec![Row::new(vec![Value::UnsignedLong(1), Value::Double(9.5)])]
+        };
+
+        let join = JoinRelation {
+            schema: Schema::new(vec![
+                Field::new("id", DataType::UnsignedLong, false),
+                Field::new("name", DataType::String, false),
+                Field::new("id", DataType::UnsignedLong, false),
+                Field::new("amount", DataType::Double, false)]),
+            left: Box::new(left),
+            right: Box::new(right),
+            left_keys: vec![Expr::TupleValue(0)],
+            right_keys: vec![Expr::TupleValue(0)]
+        };
+
+        let ctx = ExecutionContext::new();
+        let rows = join.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+
+        // bob (id 2) has no matching right row, so only alice's row survives the inner join
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values, vec![
+            Value::UnsignedLong(1), Value::String("alice".to_string()), Value::UnsignedLong(1), Value::Double(9.5)]);
+    }
+
+    #[test]
+    fn test_join_never_matches_a_null_key_against_a_null_key() {
+        // both sides have a row whose join column (e.g. an optional foreign key) is null; SQL
+        // equality says NULL = NULL is never true, so this must not come out as a matched row
+        let left = InMemoryRelation {
+            schema: Schema::new(vec![
+                Field::new("parent_id", DataType::UnsignedLong, true),
+                Field::new("name", DataType::String, false)]),
+            rows: vec![
+                Row::new(vec![Value::Null, Value::String("orphan".to_string())]),
+                Row::new(vec![Value::UnsignedLong(1), Value::String("child".to_string())])]
+        };
+        let right = InMemoryRelation {
+            schema: Schema::new(vec![
+                Field::new("id", DataType::UnsignedLong, true),
+                Field::new("label", DataType::String, false)]),
+            rows: vec![
+                Row::new(vec![Value::Null, Value::String("unassigned".to_string())]),
+                Row::new(vec![Value::UnsignedLong(1), Value::String("parent".to_string())])]
+        };
+
+        let join = JoinRelation {
+            schema: Schema::new(vec![
+                Field::new("parent_id", DataType::UnsignedLong, true),
+                Field::new("name", DataType::String, false),
+                Field::new("id", DataType::UnsignedLong, true),
+                Field::new("label", DataType::String, false)]),
+            left: Box::new(left),
+            right: Box::new(right),
+            left_keys: vec![Expr::TupleValue(0)],
+            right_keys: vec![Expr::TupleValue(0)]
+        };
+
+        let ctx = ExecutionContext::new();
+        let rows = join.scan(&ctx).collect::<Result<Vec<Row>, ExecutionError>>().unwrap();
+
+        // only the id=1 rows match; "orphan" and "unassigned" never match each other despite
+        // both having a null join key
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].values, vec![
+            Value::UnsignedLong(1), Value::String("child".to_string()), Value::UnsignedLong(1), Value::String("parent".to_string())]);
+    }
+
+    #[test]
+    fn test_resolve_qualified_column_uses_each_sides_own_schema() {
+        let orders_schema = Schema::new(vec![Field::new("id", DataType::UnsignedLong, false)]);
+        let payments_schema = Schema::new(vec![
+            Field::new("id", DataType::UnsignedLong, false),
+            Field::new("amount", DataType::Double, false)]);
+
+        let plan = LogicalPlan::Join {
+            left: Box::new(LogicalPlan::TableScan {
+                schema_name: "default".to_string(),
+                table_name: "orders".to_string(),
+                schema: orders_schema.clone()
+            }),
+            right: Box::new(LogicalPlan::TableScan {
+                schema_name: "default".to_string(),
+                table_name: "payments".to_string(),
+                schema: payments_schema.clone()
+            }),
+            left_keys: vec![Expr::TupleValue(0)],
+            right_keys: vec![Expr::TupleValue(0)],
+            schema: Schema::new(vec![
+                Field::new("id", DataType::UnsignedLong, false),
+                Field::new("id", DataType::UnsignedLong, false),
+                Field::new("amount", DataType::Double, false)])
+        };
+
+        // "payments.id" must resolve against payments' own schema (index 1, after orders'
+        // one column), not be confused with orders' same-named "id" column at index 0
+        assert_eq!(resolve_qualified_column(&plan, "orders", "id", 0), Some(0));
+        assert_eq!(resolve_qualified_column(&plan, "payments", "id", 0), Some(1));
+        assert_eq!(resolve_qualified_column(&plan, "payments", "amount", 0), Some(2));
+        assert_eq!(resolve_qualified_column(&plan, "shipments", "id", 0), None);
+    }
+
+    #[test]
+    fn test_substrait_round_trip_preserves_plan() {
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::UnsignedLong, false),
+            Field::new("amount", DataType::Double, true)]);
+
+        let plan = LogicalPlan::Projection {
+            expr: vec![
+                Expr::TupleValue(0),
+                Expr::ScalarFunction { name: "sqrt".to_string(), args: vec![Expr::TupleValue(1)] }],
+            input: Box::new(LogicalPlan::Selection {
+                expr: Expr::BinaryExpr {
+                    left: Box::new(Expr::TupleValue(1)),
+                    op: Operator::Gt,
+                    right: Box::new(Expr::Literal(Value::Double(0.0)))
+                },
+                input: Box::new(LogicalPlan::CsvFile { filename: "test/data/orders.csv".to_string(), schema: schema.clone() }),
+                schema: schema.clone()
+            }),
+            schema: schema.clone()
+        };
+
+        let bytes = substrait::plan_to_bytes(&plan).unwrap();
+        let round_tripped = substrait::bytes_to_plan(&bytes).unwrap();
+
+        match round_tripped {
+            LogicalPlan::Projection { ref expr, ref schema, ref input } => {
+                assert_eq!(expr.len(), 2);
+                assert_eq!(schema.columns.len(), 2);
+                match **input {
+                    LogicalPlan::Selection { expr: Expr::BinaryExpr { op: Operator::Gt, .. }, ref input, .. } => {
+                        match **input {
+                            LogicalPlan::CsvFile { ref filename, ref schema } => {
+                                assert_eq!(filename, "test/data/orders.csv");
+                                assert_eq!(schema.columns.len(), 2);
+                                assert_eq!(schema.columns[0].name, "id");
+                            },
+                            _ => panic!("expected the Selection's input to round-trip as a CsvFile")
+                        }
+                    },
+                    _ => panic!("expected a Selection with a Gt predicate")
+                }
+            },
+            _ => panic!("expected a Projection at the top of the round-tripped plan")
+        }
+    }
+
     #[test]
     fn test_sqrt() {
 
@@ -474,6 +2133,22 @@ mod tests {
         //TODO: check that generated file has expected contents
     }
 
+    #[test]
+    fn test_collect() {
+
+        let mut ctx = create_context();
+
+        ctx.define_function(&SqrtFunction {});
+
+        let df = ctx.sql(&"SELECT id, sqrt(id) FROM people").unwrap();
+
+        let rows = df.collect().unwrap();
+
+        assert_eq!(rows.len(), df.count().unwrap());
+        assert_eq!(rows.len(), df.take(rows.len() + 1).unwrap().len());
+        assert!(df.take(0).unwrap().is_empty());
+    }
+
     #[test]
     fn test_sql_udf_udt() {
 